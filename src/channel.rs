@@ -0,0 +1,100 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    host_api,
+    process::Process,
+    serializer::{Bincode, Serializer},
+    Mailbox, Tag,
+};
+
+/// Creates a one-way channel: a [`Sender`] that can be handed to any process — including one
+/// embedded inside a request/response message, the way [`Process`] embeds itself during encode —
+/// and a [`Receiver`] that reads back whatever gets sent, in arrival order.
+///
+/// Unlike [`Server::request`](crate::process::Server::request), neither end is a round-trip:
+/// `send` doesn't wait for anything, and `recv`/`try_recv` just drain the receiver's own mailbox.
+/// This is what lets a process fan a pipeline out to several workers, or fan several producers
+/// back in, without forcing every hop through a blocking request.
+pub fn channel<T, S = Bincode>() -> (Sender<T, S>, Receiver<T, S>) {
+    let tag = Tag::new();
+    let this_id = unsafe { host_api::process::this() };
+    let process = unsafe { Process::from(this_id) };
+    (
+        Sender { process, tag },
+        Receiver {
+            tag,
+            _serializer: PhantomData,
+        },
+    )
+}
+
+/// The sending half of a [`channel`].
+///
+/// Carries the same explicit `bound` as `Server`'s internal envelope type, and for the same
+/// reason: `S` is a marker, not something serialized, so `Sender` stays `Serialize`/`Deserialize`
+/// as long as the underlying [`Process`] is, letting it ride inside any other message.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "Process<T, S>: Serialize",
+    deserialize = "Process<T, S>: Deserialize<'de>"
+))]
+pub struct Sender<T, S = Bincode> {
+    process: Process<T, S>,
+    tag: Tag,
+}
+
+impl<T, S> Sender<T, S>
+where
+    S: Serializer<T>,
+{
+    /// Sends `value` to the matching [`Receiver`]. Never blocks.
+    pub fn send(&self, value: T) {
+        self.process.tag_send(self.tag, value);
+    }
+}
+
+/// The receiving half of a [`channel`].
+pub struct Receiver<T, S = Bincode> {
+    tag: Tag,
+    _serializer: PhantomData<(T, S)>,
+}
+
+impl<T, S> Receiver<T, S>
+where
+    S: Serializer<T>,
+{
+    /// Blocks until a value arrives.
+    pub fn recv(&self) -> T {
+        unsafe { Mailbox::<T, S>::new() }.tag_receive(&[self.tag])
+    }
+
+    /// Returns a value if one has already arrived, without blocking.
+    pub fn try_recv(&self) -> Option<T> {
+        unsafe { Mailbox::<T, S>::new() }.tag_receive_timeout(&[self.tag], Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_recv_test() {
+        let (tx, rx) = channel::<i32>();
+        tx.send(1);
+        tx.send(2);
+        assert_eq!(rx.recv(), 1);
+        assert_eq!(rx.recv(), 2);
+    }
+
+    #[test]
+    fn try_recv_test() {
+        let (tx, rx) = channel::<i32>();
+        assert_eq!(rx.try_recv(), None);
+        tx.send(1);
+        assert_eq!(rx.try_recv(), Some(1));
+    }
+}
@@ -0,0 +1,263 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::server::{Server, Timeout};
+use crate::LunaticError;
+
+/// A single request/response pair a [`Dispatcher`]-backed server knows how to answer.
+///
+/// Each type implementing this trait gets its own slot in the wire envelope (see
+/// [`Dispatcher::on`]), the way an LSP server registers one handler per method name. `DISCRIMINANT`
+/// must be unique among every request type registered on the same dispatcher.
+pub trait DispatchRequest: Serialize + DeserializeOwned {
+    type Response: Serialize + DeserializeOwned;
+
+    const DISCRIMINANT: u32;
+}
+
+/// The envelope actually sent over the wire: which [`DispatchRequest`] `payload` decodes as, plus
+/// its bincode-encoded bytes.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    discriminant: u32,
+    payload: Vec<u8>,
+}
+
+/// Returned instead of a response when a [`Dispatcher`] can't produce one.
+///
+/// Both variants carry the discriminant involved so the caller can tell which request type was at
+/// fault, e.g. because the caller and the server were built from different versions of the
+/// request set.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum DispatchError {
+    /// No registered handler claims this discriminant.
+    UnknownDiscriminant(u32),
+    /// A handler is registered for this discriminant, but the payload didn't decode as its
+    /// request type.
+    BadPayload(u32),
+}
+
+type DispatchResult = Result<Vec<u8>, DispatchError>;
+
+// A monomorphized, capture-free trampoline: decodes `payload` as `M`, calls the plain `fn` hiding
+// behind `handler`, and re-encodes the response. Generated per `(C, M)` pair so it can be sent
+// across the spawn boundary as a bare function pointer, the same way `Server`'s own handler is.
+//
+// Returns a `DispatchError` instead of panicking on a bad payload: this runs inline in the
+// dispatcher's own process loop (see `dispatch_loop`), so a decode failure here must not trap the
+// whole process out from under every other in-flight and future request.
+fn trampoline<C, M>(handler: usize, state: &mut C, payload: &[u8]) -> Result<Vec<u8>, DispatchError>
+where
+    M: DispatchRequest,
+{
+    let handler: fn(&mut C, M) -> M::Response = unsafe { std::mem::transmute(handler) };
+    let message: M =
+        bincode::deserialize(payload).map_err(|_| DispatchError::BadPayload(M::DISCRIMINANT))?;
+    let response = handler(state, message);
+    Ok(bincode::serialize(&response).expect("dispatch response must be serializable"))
+}
+
+type Route = (u32, usize, usize);
+
+/// Builds up the set of request types a single process loop will answer against one shared state
+/// `C`, then spawns it.
+pub struct Dispatcher<C> {
+    routes: Vec<Route>,
+    _state: PhantomData<C>,
+}
+
+impl<C> Dispatcher<C> {
+    pub fn new() -> Self {
+        Dispatcher {
+            routes: Vec::new(),
+            _state: PhantomData,
+        }
+    }
+
+    /// Registers `handler` to answer every request of type `M`.
+    pub fn on<M>(mut self, handler: fn(state: &mut C, request: M) -> M::Response) -> Self
+    where
+        M: DispatchRequest + 'static,
+    {
+        self.routes.push((
+            M::DISCRIMINANT,
+            trampoline::<C, M> as usize,
+            handler as usize,
+        ));
+        self
+    }
+
+    /// Spawns the dispatcher with `state` as its initial shared state.
+    pub fn spawn(self, state: C) -> Result<DispatcherRef<C>, LunaticError>
+    where
+        C: Serialize + DeserializeOwned,
+    {
+        let inner = crate::process::spawn::<Server<Envelope, DispatchResult>, _>(
+            (state, self.routes),
+            dispatch_loop::<C>,
+        )?;
+        Ok(DispatcherRef {
+            inner,
+            _state: PhantomData,
+        })
+    }
+}
+
+impl<C> Default for Dispatcher<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn dispatch_loop<C>(state: &mut (C, Vec<Route>), envelope: Envelope) -> DispatchResult {
+    let (state, routes) = state;
+    match routes.iter().find(|(d, _, _)| *d == envelope.discriminant) {
+        Some(&(_, trampoline_ptr, handler_ptr)) => {
+            let trampoline: fn(usize, &mut C, &[u8]) -> Result<Vec<u8>, DispatchError> =
+                unsafe { std::mem::transmute(trampoline_ptr) };
+            trampoline(handler_ptr, state, &envelope.payload)
+        }
+        None => Err(DispatchError::UnknownDiscriminant(envelope.discriminant)),
+    }
+}
+
+/// A handle to a spawned [`Dispatcher`], returned by [`Dispatcher::spawn`].
+pub struct DispatcherRef<C> {
+    inner: Server<Envelope, DispatchResult>,
+    _state: PhantomData<C>,
+}
+
+impl<C> DispatcherRef<C> {
+    /// Sends a request of type `M` and blocks for its response, the same way
+    /// [`Server::request`](super::server::Server::request) does for a single-request server.
+    ///
+    /// Panics if the server doesn't have a handler registered for `M` — that should only happen
+    /// if the caller and the server were built from request sets that have drifted apart.
+    pub fn request<M>(&self, message: M) -> M::Response
+    where
+        M: DispatchRequest,
+    {
+        let envelope = Envelope {
+            discriminant: M::DISCRIMINANT,
+            payload: bincode::serialize(&message).expect("dispatch request must be serializable"),
+        };
+        match self.inner.request(envelope) {
+            Ok(payload) => bincode::deserialize(&payload).expect("corrupt dispatch response"),
+            Err(DispatchError::UnknownDiscriminant(discriminant)) => {
+                panic!("no handler registered for discriminant {discriminant}")
+            }
+            Err(DispatchError::BadPayload(discriminant)) => {
+                panic!("payload for discriminant {discriminant} didn't decode as the registered handler's request type")
+            }
+        }
+    }
+
+    /// Like [`request`](DispatcherRef::request), but bounds the wait with `timeout` instead of
+    /// blocking forever if the dispatcher process traps or never replies.
+    ///
+    /// There is no `AbstractProcess`/`ProcessRequest` in this crate for a deadline to mirror onto
+    /// directly, so this is that support surfaced on the one multi-request construct that does
+    /// exist: a `Dispatcher`-backed server.
+    ///
+    /// Panics if the server doesn't have a handler registered for `M`, the same as
+    /// [`request`](DispatcherRef::request); returns `Err(Timeout)` instead of panicking if
+    /// `timeout` elapses first.
+    pub fn request_timeout<M>(&self, message: M, timeout: Duration) -> Result<M::Response, Timeout>
+    where
+        M: DispatchRequest,
+    {
+        let envelope = Envelope {
+            discriminant: M::DISCRIMINANT,
+            payload: bincode::serialize(&message).expect("dispatch request must be serializable"),
+        };
+        match self.inner.request_timeout(envelope, timeout)? {
+            Ok(payload) => Ok(bincode::deserialize(&payload).expect("corrupt dispatch response")),
+            Err(DispatchError::UnknownDiscriminant(discriminant)) => {
+                panic!("no handler registered for discriminant {discriminant}")
+            }
+            Err(DispatchError::BadPayload(discriminant)) => {
+                panic!("payload for discriminant {discriminant} didn't decode as the registered handler's request type")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Add(i32, i32);
+
+    impl DispatchRequest for Add {
+        type Response = i32;
+        const DISCRIMINANT: u32 = 0;
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Greet(String);
+
+    impl DispatchRequest for Greet {
+        type Response = String;
+        const DISCRIMINANT: u32 = 1;
+    }
+
+    fn spawn_test_dispatcher() -> DispatcherRef<()> {
+        Dispatcher::new()
+            .on::<Add>(|_, Add(a, b)| a + b)
+            .on::<Greet>(|_, Greet(name)| format!("hello, {name}"))
+            .spawn(())
+            .unwrap()
+    }
+
+    #[test]
+    fn routes_by_discriminant_test() {
+        let dispatcher = spawn_test_dispatcher();
+        assert_eq!(dispatcher.request(Add(1, 2)), 3);
+        assert_eq!(
+            dispatcher.request(Greet("world".to_string())),
+            "hello, world"
+        );
+    }
+
+    #[test]
+    fn bad_payload_on_known_discriminant_does_not_trap_test() {
+        let dispatcher = Dispatcher::<()>::new().on::<Add>(|_, Add(a, b)| a + b);
+        let mut state = ((), dispatcher.routes);
+
+        // Same discriminant as `Add`, but too short to decode as one: simulates the schema-drift
+        // case where a known discriminant's payload no longer matches the registered type.
+        let envelope = Envelope {
+            discriminant: Add::DISCRIMINANT,
+            payload: vec![0xff, 0xff, 0xff, 0xff],
+        };
+        assert!(matches!(
+            dispatch_loop(&mut state, envelope),
+            Err(DispatchError::BadPayload(d)) if d == Add::DISCRIMINANT
+        ));
+
+        // The dispatch loop this simulates is still alive to answer the next request.
+        let envelope = Envelope {
+            discriminant: Add::DISCRIMINANT,
+            payload: bincode::serialize(&Add(1, 2)).unwrap(),
+        };
+        let response = dispatch_loop(&mut state, envelope).unwrap();
+        assert_eq!(bincode::deserialize::<i32>(&response).unwrap(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "no handler registered")]
+    fn unregistered_discriminant_panics_test() {
+        #[derive(Serialize, Deserialize)]
+        struct Unregistered;
+
+        impl DispatchRequest for Unregistered {
+            type Response = ();
+            const DISCRIMINANT: u32 = 2;
+        }
+
+        spawn_test_dispatcher().request(Unregistered);
+    }
+}
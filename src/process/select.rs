@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{host_api, serializer::Serializer, Tag};
+
+/// Waits on the first of several in-flight [`Tag`]s to receive a message, without losing the
+/// ones that didn't fire.
+///
+/// Build one with [`Select::new`], register a branch per tag with [`Select::branch`], then call
+/// [`Select::select`]. Branches that don't win stay outstanding: their tag is never consumed, so a
+/// later `select` (or a plain `tag_receive`) can still pick them up.
+///
+/// `receive_any` both matches a tag *and* leaves its message as the one currently being decoded,
+/// the same way `Mailbox::receive` does before handing off to `S::decode`. So the winning branch
+/// decodes straight out of that already-matched message instead of issuing a second, separate
+/// receive for its tag — a tag only fires once, and a second receive on it would just block
+/// forever waiting for a message that already came and went.
+pub struct Select<T> {
+    tags: Vec<Tag>,
+    branches: HashMap<Tag, Box<dyn FnOnce() -> T>>,
+    timeout: Option<Duration>,
+}
+
+impl<T> Select<T> {
+    pub fn new() -> Self {
+        Select {
+            tags: Vec::new(),
+            branches: HashMap::new(),
+            timeout: None,
+        }
+    }
+
+    /// Registers a branch: if `tag` is the one that wins, the message already matched under it is
+    /// decoded as `P` and passed through `decode` to produce the branch's result.
+    pub fn branch<P, S>(mut self, tag: Tag, decode: impl FnOnce(P) -> T + 'static) -> Self
+    where
+        S: Serializer<P>,
+    {
+        self.branches
+            .insert(tag, Box::new(move || decode(S::decode().unwrap())));
+        self.tags.push(tag);
+        self
+    }
+
+    /// Bounds the whole select with a deadline; if no branch wins in time, [`Select::select`]
+    /// returns `None` and every branch remains outstanding.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Blocks until the first registered branch wins, returning its decoded result.
+    pub fn select(mut self) -> Option<T> {
+        let tag_ids: Vec<i64> = self.tags.iter().map(Tag::id).collect();
+        let winner = receive_any(&tag_ids, self.timeout.unwrap_or(Duration::MAX))?;
+        let decode = self.branches.remove(&winner)?;
+        Some(decode())
+    }
+}
+
+// Blocks (up to `timeout`) on the union of `tags`, returning the tag that matched. As with
+// `Mailbox::receive`, matching also makes that message the one `S::decode` reads next, so it must
+// be decoded before another `receive_any`/`tag_receive` call runs; non-winning tags stay untouched
+// and are never consumed.
+fn receive_any(tags: &[i64], timeout: Duration) -> Option<Tag> {
+    let timeout_ms = timeout.as_millis().min(u64::MAX as u128) as u64;
+    let matched = unsafe { host_api::message::receive(tags.as_ptr(), tags.len(), timeout_ms) };
+    if matched < 0 {
+        None
+    } else {
+        Some(Tag::from(matched))
+    }
+}
+
+impl<T> Default for Select<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Process;
+    use crate::serializer::Bincode;
+    use crate::Mailbox;
+
+    #[test]
+    fn select_picks_the_tag_that_fired_test() {
+        let this_id = unsafe { host_api::process::this() };
+        let this_proc: Process<i32, Bincode> = unsafe { Process::from(this_id) };
+
+        let winning_tag = Tag::new();
+        let losing_tag = Tag::new();
+        this_proc.tag_send(winning_tag, 7);
+
+        let result = Select::new()
+            .branch::<i32, Bincode>(winning_tag, |v| v)
+            .branch::<i32, Bincode>(losing_tag, |v| v)
+            .select();
+        assert_eq!(result, Some(7));
+
+        // The losing branch's tag was never consumed, so a plain receive still picks it up.
+        this_proc.tag_send(losing_tag, 9);
+        assert_eq!(
+            unsafe { Mailbox::<i32, Bincode>::new() }.tag_receive(&[losing_tag]),
+            9
+        );
+    }
+
+    #[test]
+    fn select_times_out_test() {
+        let tag = Tag::new();
+        let result = Select::new()
+            .branch::<i32, Bincode>(tag, |v: i32| v)
+            .timeout(Duration::from_millis(50))
+            .select();
+        assert_eq!(result, None);
+    }
+}
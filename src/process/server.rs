@@ -1,4 +1,7 @@
 use std::marker::PhantomData;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
 
 use super::{IntoProcess, IntoProcessLink, Process};
 use crate::{
@@ -8,11 +11,33 @@ use crate::{
     LunaticError, Mailbox, Tag,
 };
 
+/// Returned by [`Server::request_timeout`] when `timeout` elapses before a response arrives.
+///
+/// Unlike [`Server::request`], which blocks forever, this lets a caller recover from a server
+/// that traps, deadlocks or is simply slow instead of hanging with it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Timeout;
+
+// The envelope `Server`'s mailbox actually receives. Wrapping requests and the shutdown signal in
+// one type lets a single process loop answer both without trapping on an unexpected message shape.
+//
+// The explicit `bound` is needed because `S` is only ever a marker for which `Serializer` impl to
+// use, not something that's itself serialized; the derive macro can't see that.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "Process<R, S>: Serialize, Process<(), S>: Serialize, M: Serialize",
+    deserialize = "Process<R, S>: Deserialize<'de>, Process<(), S>: Deserialize<'de>, M: Deserialize<'de>"
+))]
+enum ServerMessage<M, R, S> {
+    Request(Process<R, S>, Tag, M),
+    Shutdown(Process<(), S>, Tag),
+}
+
 /// A [`Server`] is a simple process spawned from a function that can maintain a state, runs in a
 /// loop and answers requests sent to it.
 pub struct Server<M, R, S = Bincode>
 where
-    S: Serializer<(Process<R, S>, Tag, M)> + Serializer<R>,
+    S: Serializer<ServerMessage<M, R, S>> + Serializer<R> + Serializer<()>,
 {
     id: u64,
     serializer_type: PhantomData<(M, R, S)>,
@@ -20,7 +45,7 @@ where
 
 impl<M, R, S> Server<M, R, S>
 where
-    S: Serializer<(Process<R, S>, Tag, M)> + Serializer<R>,
+    S: Serializer<ServerMessage<M, R, S>> + Serializer<R> + Serializer<()>,
 {
     /// Returns a globally unique process ID.
     pub fn id(&self) -> u128 {
@@ -37,13 +62,71 @@ where
         let this_id = unsafe { host_api::process::this() };
         let this_proc: Process<R, S> = unsafe { Process::from(this_id) };
         // During serialization resources will add themself to the message.
-        S::encode(&(this_proc, tag, message)).unwrap();
+        S::encode(&ServerMessage::Request(this_proc, tag, message)).unwrap();
         // Send it!
         unsafe { host_api::message::send(self.id) };
         // Wait on response
         unsafe { Mailbox::<R, S>::new() }.tag_receive(&[tag])
     }
 
+    /// Like [`request`](Server::request), but bounds the wait with `timeout` instead of blocking
+    /// forever if the server process traps or never replies.
+    ///
+    /// There's no equivalent on the higher-level `AbstractProcess`/`ProcessRequest` path (see
+    /// `examples/request_response.rs`) because those types have no implementation anywhere in this
+    /// crate yet — adding a deadline there is out of scope until they exist. Until then,
+    /// [`DispatcherRef::request_timeout`](super::dispatcher::DispatcherRef::request_timeout) is the
+    /// closest in-tree high-level equivalent.
+    pub fn request_timeout(&self, message: M, timeout: Duration) -> Result<R, Timeout> {
+        let tag = Tag::new();
+        // Create new message.
+        unsafe { host_api::message::create_data(1, 0) };
+        // Create reference to self
+        let this_id = unsafe { host_api::process::this() };
+        let this_proc: Process<R, S> = unsafe { Process::from(this_id) };
+        // During serialization resources will add themself to the message.
+        S::encode(&ServerMessage::Request(this_proc, tag, message)).unwrap();
+        // Send it!
+        unsafe { host_api::message::send(self.id) };
+        // Wait on response, but give up the tag and return instead of blocking forever.
+        unsafe { Mailbox::<R, S>::new() }
+            .tag_receive_timeout(&[tag], timeout)
+            .ok_or(Timeout)
+    }
+
+    /// Signals the server to stop its loop once it has answered the requests that reached it
+    /// before this signal, without waiting for it to do so. See [`Server::block_until_shutdown`]
+    /// to wait for that.
+    pub fn shutdown(&self) {
+        let tag = Tag::new();
+        unsafe { host_api::message::create_data(1, 0) };
+        let this_id = unsafe { host_api::process::this() };
+        let this_proc: Process<(), S> = unsafe { Process::from(this_id) };
+        S::encode(&ServerMessage::<M, R, S>::Shutdown(this_proc, tag)).unwrap();
+        unsafe { host_api::message::send(self.id) };
+    }
+
+    /// Signals the server to stop, runs its `on_shutdown` hook (if one was registered at spawn
+    /// time) against the final state, and blocks until the server acknowledges the shutdown.
+    ///
+    /// The server sends that acknowledgement after `on_shutdown` has run, as its last step before
+    /// leaving its loop; this method returns on the acknowledgement and does not wait for the
+    /// server process itself to exit.
+    ///
+    /// This does not hand the final state back to the caller: `Server<M, R, S>` never names its
+    /// captured state type `C`, so there's nowhere for it to go without widening every `Server`
+    /// signature to carry it. Read it out via `on_shutdown` instead (e.g. send it somewhere before
+    /// returning) if the caller needs it.
+    pub fn block_until_shutdown(&self) {
+        let tag = Tag::new();
+        unsafe { host_api::message::create_data(1, 0) };
+        let this_id = unsafe { host_api::process::this() };
+        let this_proc: Process<(), S> = unsafe { Process::from(this_id) };
+        S::encode(&ServerMessage::<M, R, S>::Shutdown(this_proc, tag)).unwrap();
+        unsafe { host_api::message::send(self.id) };
+        unsafe { Mailbox::<(), S>::new() }.tag_receive(&[tag]);
+    }
+
     fn send_init<C>(&self, message: C)
     where
         S: Serializer<C>,
@@ -59,7 +142,7 @@ where
 
 impl<C, M, R, S> IntoProcess<C> for Server<M, R, S>
 where
-    S: Serializer<C> + Serializer<(Process<R, S>, Tag, M)> + Serializer<R>,
+    S: Serializer<C> + Serializer<ServerMessage<M, R, S>> + Serializer<R> + Serializer<()>,
 {
     type Handler = fn(state: &mut C, request: M) -> R;
 
@@ -67,13 +150,13 @@ where
     where
         Self: Sized,
     {
-        spawn(false, state, handler)
+        spawn(false, state, handler, None)
     }
 }
 
 impl<C, M, R, S> IntoProcessLink<C> for Server<M, R, S>
 where
-    S: Serializer<C> + Serializer<(Process<R, S>, Tag, M)> + Serializer<R>,
+    S: Serializer<C> + Serializer<ServerMessage<M, R, S>> + Serializer<R> + Serializer<()>,
 {
     type Handler = fn(state: &mut C, request: M) -> R;
 
@@ -81,7 +164,23 @@ where
     where
         Self: Sized,
     {
-        spawn(true, state, handler)
+        spawn(true, state, handler, None)
+    }
+}
+
+impl<C, M, R, S> Server<M, R, S>
+where
+    S: Serializer<C> + Serializer<ServerMessage<M, R, S>> + Serializer<R> + Serializer<()>,
+{
+    /// Like spawning through [`IntoProcess`], but registers `on_shutdown` to run against the
+    /// server's state right before it tears down in response to [`Server::shutdown`] or
+    /// [`Server::block_until_shutdown`].
+    pub fn spawn_with_shutdown(
+        state: C,
+        handler: fn(state: &mut C, request: M) -> R,
+        on_shutdown: fn(state: &mut C),
+    ) -> Result<Server<M, R, S>, LunaticError> {
+        spawn(false, state, handler, Some(on_shutdown))
     }
 }
 
@@ -93,13 +192,20 @@ fn spawn<C, M, R, S>(
     link: bool,
     state: C,
     handler: fn(state: &mut C, request: M) -> R,
+    on_shutdown: Option<fn(state: &mut C)>,
 ) -> Result<Server<M, R, S>, LunaticError>
 where
-    S: Serializer<C> + Serializer<(Process<R, S>, Tag, M)> + Serializer<R>,
+    S: Serializer<C> + Serializer<ServerMessage<M, R, S>> + Serializer<R> + Serializer<()>,
 {
     let (type_helper, handler) = (type_helper_wrapper::<C, M, R, S> as i32, handler as i32);
+    // `0` is never a valid function pointer, so it doubles as the "no hook registered" sentinel.
+    let on_shutdown = on_shutdown.map(|f| f as i32).unwrap_or(0);
 
-    let params = params_to_vec(&[Param::I32(type_helper), Param::I32(handler)]);
+    let params = params_to_vec(&[
+        Param::I32(type_helper),
+        Param::I32(handler),
+        Param::I32(on_shutdown),
+    ]);
     let mut id = 0;
     let func = "_lunatic_spawn_server_by_index";
     let link = match link {
@@ -140,9 +246,9 @@ where
 }
 
 // Type helper
-fn type_helper_wrapper<C, M, R, S>(function: usize)
+fn type_helper_wrapper<C, M, R, S>(function: usize, on_shutdown: usize)
 where
-    S: Serializer<C> + Serializer<(Process<R, S>, Tag, M)> + Serializer<R>,
+    S: Serializer<C> + Serializer<ServerMessage<M, R, S>> + Serializer<R> + Serializer<()>,
 {
     // If the captured variable is of size 0, don't wait on it.
     let mut state = if std::mem::size_of::<C>() == 0 {
@@ -150,27 +256,46 @@ where
     } else {
         unsafe { Mailbox::<C, S>::new() }.receive()
     };
-    let mailbox: Mailbox<(Process<R, S>, Tag, M), S> = unsafe { Mailbox::new() };
+    let mailbox: Mailbox<ServerMessage<M, R, S>, S> = unsafe { Mailbox::new() };
     let handler: fn(state: &mut C, request: M) -> R = unsafe { std::mem::transmute(function) };
+    let on_shutdown: Option<fn(state: &mut C)> = if on_shutdown == 0 {
+        None
+    } else {
+        Some(unsafe { std::mem::transmute(on_shutdown) })
+    };
 
-    // Run server forever and respond to requests.
+    // Run server forever and respond to requests, until told to shut down.
     loop {
-        let (sender, tag, message) = mailbox.receive();
-        let response = handler(&mut state, message);
-        sender.tag_send(tag, response);
+        match mailbox.receive() {
+            ServerMessage::Request(sender, tag, message) => {
+                let response = handler(&mut state, message);
+                sender.tag_send(tag, response);
+            }
+            ServerMessage::Shutdown(sender, tag) => {
+                if let Some(on_shutdown) = on_shutdown {
+                    on_shutdown(&mut state);
+                }
+                sender.tag_send(tag, ());
+                break;
+            }
+        }
     }
 }
 
 #[export_name = "_lunatic_spawn_server_by_index"]
-extern "C" fn _lunatic_spawn_server_by_index(type_helper: usize, function: usize) {
-    let type_helper: fn(usize) = unsafe { std::mem::transmute(type_helper) };
-    type_helper(function);
+extern "C" fn _lunatic_spawn_server_by_index(
+    type_helper: usize,
+    function: usize,
+    on_shutdown: usize,
+) {
+    let type_helper: fn(usize, usize) = unsafe { std::mem::transmute(type_helper) };
+    type_helper(function, on_shutdown);
 }
 
 // Processes are equal if their UUID is equal.
 impl<M, R, S> PartialEq for Server<M, R, S>
 where
-    S: Serializer<(Process<R, S>, Tag, M)> + Serializer<R>,
+    S: Serializer<ServerMessage<M, R, S>> + Serializer<R> + Serializer<()>,
 {
     fn eq(&self, other: &Self) -> bool {
         self.id() == other.id()
@@ -179,7 +304,7 @@ where
 
 impl<M, R, S> std::fmt::Debug for Server<M, R, S>
 where
-    S: Serializer<(Process<R, S>, Tag, M)> + Serializer<R>,
+    S: Serializer<ServerMessage<M, R, S>> + Serializer<R> + Serializer<()>,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Process").field("uuid", &self.id()).finish()
@@ -189,6 +314,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::channel::channel;
     use crate::process::{sleep, spawn, spawn_link};
 
     #[test]
@@ -203,6 +329,58 @@ mod tests {
         assert_eq!(child.request(3), 6);
     }
 
+    #[test]
+    fn request_timeout_test() {
+        let child = spawn::<Server<i32, i32>, _>(0, |state, message| {
+            *state += message;
+            *state
+        })
+        .unwrap();
+        assert_eq!(child.request_timeout(1, Duration::from_millis(100)), Ok(1));
+        assert_eq!(child.request_timeout(2, Duration::from_millis(100)), Ok(3));
+    }
+
+    #[test]
+    fn request_timeout_elapses_test() {
+        let child = spawn::<Server<(), ()>, _>((), |_, _| {
+            // Never responds in time.
+            sleep(1_000);
+        })
+        .unwrap();
+        assert_eq!(
+            child.request_timeout((), Duration::from_millis(100)),
+            Err(Timeout)
+        );
+    }
+
+    #[test]
+    fn shutdown_test() {
+        // `on_shutdown` is a plain `fn`, so it can't capture anything; route its observable
+        // effect back to this test through a channel embedded in the server's own state instead.
+        let (tx, rx) = channel::<i32>();
+        let child = Server::<i32, i32>::spawn_with_shutdown(
+            (0, tx),
+            |(state, _), message| {
+                *state += message;
+                *state
+            },
+            |(state, tx)| tx.send(*state),
+        )
+        .unwrap();
+        assert_eq!(child.request(1), 1);
+        assert_eq!(child.request(2), 3);
+        // Stops the loop, runs `on_shutdown` against the final state, and blocks until the
+        // server acknowledges, which it does right before leaving its loop.
+        child.block_until_shutdown();
+        // `on_shutdown` actually ran, against the accumulated state.
+        assert_eq!(rx.recv(), 3);
+        // The loop actually exited: nothing is left to answer a further request.
+        assert_eq!(
+            child.request_timeout(4, Duration::from_millis(100)),
+            Err(Timeout)
+        );
+    }
+
     #[test]
     fn spawn_link_test() {
         // There is no real way of testing traps for now, at least not until this is resolved:
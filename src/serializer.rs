@@ -0,0 +1,151 @@
+use std::io::{Read, Write};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::host_api;
+
+/// Encodes a value of type `T` into the currently open outbound message, or decodes one out of
+/// the currently received inbound message.
+///
+/// [`Server`](crate::process::Server) and friends are generic over this trait so the wire format
+/// used at a process boundary can be swapped without touching the message types themselves.
+pub trait Serializer<T> {
+    fn encode(message: &T) -> Result<(), Box<dyn std::error::Error>>;
+    fn decode() -> Result<T, Box<dyn std::error::Error>>;
+}
+
+// A `Write`/`Read` adapter streaming bytes directly into/out of the current lunatic message
+// buffer, so serializer implementations can be written against plain `std::io` and ignore the
+// host call plumbing.
+struct MessageStream;
+
+impl Write for MessageStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(unsafe { host_api::message::write_data(buf.as_ptr(), buf.len()) })
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Read for MessageStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(unsafe { host_api::message::read_data(buf.as_mut_ptr(), buf.len()) })
+    }
+}
+
+/// The default [`Serializer`], backed by [`bincode`]. Compact, but not self-describing: both ends
+/// of a message must agree on the exact layout of `T`.
+pub struct Bincode {}
+
+impl<T> Serializer<T> for Bincode
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn encode(message: &T) -> Result<(), Box<dyn std::error::Error>> {
+        bincode::serialize_into(MessageStream, message)?;
+        Ok(())
+    }
+
+    fn decode() -> Result<T, Box<dyn std::error::Error>> {
+        Ok(bincode::deserialize_from(MessageStream)?)
+    }
+}
+
+/// A self-describing [`Serializer`] backed by [CBOR](https://cbor.io). Unlike [`Bincode`],
+/// tolerates some schema drift between the two ends of a message.
+pub struct Cbor {}
+
+impl<T> Serializer<T> for Cbor
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn encode(message: &T) -> Result<(), Box<dyn std::error::Error>> {
+        serde_cbor::to_writer(MessageStream, message)?;
+        Ok(())
+    }
+
+    fn decode() -> Result<T, Box<dyn std::error::Error>> {
+        Ok(serde_cbor::from_reader(MessageStream)?)
+    }
+}
+
+/// A self-describing [`Serializer`] backed by [MessagePack](https://msgpack.org). Like [`Cbor`],
+/// but more compact.
+pub struct MessagePack {}
+
+impl<T> Serializer<T> for MessagePack
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn encode(message: &T) -> Result<(), Box<dyn std::error::Error>> {
+        // `rmp_serde::encode::write`'s default mode encodes structs as positional arrays, which
+        // would silently misalign on an added/removed field instead of tolerating it. Encoding as
+        // a map instead keeps field names on the wire, the same as `Cbor`.
+        let mut serializer = rmp_serde::Serializer::new(MessageStream).with_struct_map();
+        message.serialize(&mut serializer)?;
+        Ok(())
+    }
+
+    fn decode() -> Result<T, Box<dyn std::error::Error>> {
+        Ok(rmp_serde::decode::from_read(MessageStream)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{spawn, Server};
+    use serde::Deserialize;
+
+    #[test]
+    fn cbor_round_trip_test() {
+        let child = spawn::<Server<i32, i32, Cbor>, _>(0, |state, message| {
+            *state += message;
+            *state
+        })
+        .unwrap();
+        assert_eq!(child.request(1), 1);
+        assert_eq!(child.request(2), 3);
+    }
+
+    #[test]
+    fn message_pack_round_trip_test() {
+        let child = spawn::<Server<i32, i32, MessagePack>, _>(0, |state, message| {
+            *state += message;
+            *state
+        })
+        .unwrap();
+        assert_eq!(child.request(1), 1);
+        assert_eq!(child.request(2), 3);
+    }
+
+    #[derive(Serialize)]
+    struct RequestV1 {
+        id: i32,
+    }
+
+    #[derive(Deserialize)]
+    struct RequestV2 {
+        id: i32,
+        #[serde(default)]
+        retry: Option<bool>,
+    }
+
+    #[test]
+    fn cbor_tolerates_an_added_optional_field_test() {
+        let bytes = serde_cbor::to_vec(&RequestV1 { id: 7 }).unwrap();
+        let decoded: RequestV2 = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.id, 7);
+        assert_eq!(decoded.retry, None);
+    }
+
+    #[test]
+    fn message_pack_tolerates_an_added_optional_field_test() {
+        let bytes = rmp_serde::to_vec_named(&RequestV1 { id: 7 }).unwrap();
+        let decoded: RequestV2 = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.id, 7);
+        assert_eq!(decoded.retry, None);
+    }
+}